@@ -1,6 +1,9 @@
 use lalrpop_intern::InternedString;
 use std::fmt;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Span {
     pub lo: usize,
@@ -9,23 +12,52 @@ pub struct Span {
 
 impl Span {
     pub fn new(lo: usize, hi: usize) -> Self {
-        Span { lo: lo, hi: hi }
+        Span { lo, hi }
+    }
+
+    /// Merges two spans, covering the range from `self`'s start to
+    /// `other`'s end. Used while parsing to combine the spans of
+    /// sub-productions into the span of the production they make up.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.lo, other.hi)
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// Wraps an AST node together with the span of source text it was parsed
+/// from, following rustc's `Spanned<T>`. Lowering and type-check errors can
+/// report `span.lo..span.hi` so the CLI/test harness can render carets
+/// under the offending source.
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned { node, span }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Program {
     pub items: Vec<Item>
 }
 
+pub type Item = Spanned<ItemData>;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub enum Item {
+pub enum ItemData {
     StructDefn(StructDefn),
     TraitDefn(TraitDefn),
     Impl(Impl),
     Clause(Clause),
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct StructDefn {
     pub name: Identifier,
@@ -35,12 +67,14 @@ pub struct StructDefn {
     pub flags: StructFlags,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct StructFlags {
     pub external: bool,
     pub fundamental: bool,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct TraitDefn {
     pub name: Identifier,
@@ -50,6 +84,7 @@ pub struct TraitDefn {
     pub flags: TraitFlags,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct TraitFlags {
     pub auto: bool,
@@ -58,6 +93,7 @@ pub struct TraitFlags {
     pub deref: bool,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct AssocTyDefn {
     pub name: Identifier,
@@ -66,18 +102,46 @@ pub struct AssocTyDefn {
     pub where_clauses: Vec<QuantifiedWhereClause>,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum ParameterKind {
     Ty(Identifier),
     Lifetime(Identifier),
+    Const(Identifier, Ty),
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Parameter {
     Ty(Ty),
     Lifetime(Lifetime),
+    Const(Const),
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// A const generic argument, e.g. the `3` in `Array<u32, 3>` or the `N` in
+/// `Array<T, N>` when `N` is itself a const parameter.
+pub struct Const {
+    pub kind: ConstantKind,
+    pub ty: Ty,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ConstantKind {
+    /// A reference to a bound or generic const parameter.
+    Bound(Identifier),
+    /// A concrete literal value, stored as a sign-extended `i128` so it can
+    /// represent any value a const parameter's declared type (`Const.ty`,
+    /// e.g. `i32`, `i64`, `usize`) might take, including negative literals
+    /// like the `-1` in `Foo<i32, -1>` — not just non-negative array lengths.
+    /// Does not cover the full range of `u128` (values `>= 2^127`); such
+    /// values would need a wider or signedness-tagged representation.
+    Value(i128),
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 /// An inline bound, e.g. `: Foo<K>` in `impl<K, T: Foo<K>> SomeType<T>`.
 pub enum InlineBound {
@@ -85,6 +149,7 @@ pub enum InlineBound {
     ProjectionEqBound(ProjectionEqBound),
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 /// Represents a trait bound on e.g. a type or type parameter.
 /// Does not know anything about what it's binding.
@@ -93,6 +158,7 @@ pub struct TraitBound {
     pub args_no_self: Vec<Parameter>,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 /// Represents a projection equality bound on e.g. a type or type parameter.
 /// Does not know anything about what it's binding.
@@ -103,10 +169,12 @@ pub struct ProjectionEqBound {
     pub value: Ty,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Kind {
     Ty,
     Lifetime,
+    Const,
 }
 
 impl fmt::Display for Kind {
@@ -115,11 +183,15 @@ impl fmt::Display for Kind {
             match *self {
                 Kind::Ty => "type",
                 Kind::Lifetime => "lifetime",
+                Kind::Const => "const",
             }
         )
     }
 }
 
+/// A parameter, or parameter kind, must agree in `Kind` when they are
+/// matched against one another (e.g. a const parameter only ever unifies
+/// with a const argument, never with a type or lifetime).
 pub trait Kinded {
     fn kind(&self) -> Kind;
 }
@@ -129,6 +201,7 @@ impl Kinded for ParameterKind {
         match *self {
             ParameterKind::Ty(_) => Kind::Ty,
             ParameterKind::Lifetime(_) => Kind::Lifetime,
+            ParameterKind::Const(_, _) => Kind::Const,
         }
     }
 }
@@ -138,10 +211,12 @@ impl Kinded for Parameter {
         match *self {
             Parameter::Ty(_) => Kind::Ty,
             Parameter::Lifetime(_) => Kind::Lifetime,
+            Parameter::Const(_) => Kind::Const,
         }
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Impl {
     pub parameter_kinds: Vec<ParameterKind>,
@@ -150,6 +225,7 @@ pub struct Impl {
     pub assoc_ty_values: Vec<AssocTyValue>,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct AssocTyValue {
     pub name: Identifier,
@@ -157,8 +233,11 @@ pub struct AssocTyValue {
     pub value: Ty,
 }
 
+pub type Ty = Spanned<TyData>;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub enum Ty {
+pub enum TyData {
     Id {
         name: Identifier,
     },
@@ -175,16 +254,61 @@ pub enum Ty {
     ForAll {
         lifetime_names: Vec<Identifier>,
         ty: Box<Ty>
-    }
+    },
+    Tuple {
+        components: Vec<Ty>,
+    },
+    Array {
+        ty: Box<Ty>,
+        len: Box<Const>,
+    },
+    Slice {
+        ty: Box<Ty>,
+    },
+    Ref {
+        lifetime: Lifetime,
+        mutability: Mutability,
+        ty: Box<Ty>,
+    },
+    RawPtr {
+        mutability: Mutability,
+        ty: Box<Ty>,
+    },
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Lifetime {
     Id {
         name: Identifier,
-    }
+    },
+    /// A region bound by an enclosing `ForAll`/`Exists`, identified by the
+    /// de Bruijn depth of the binder and its position within it.
+    BoundVar {
+        debruijn: u32,
+        index: u32,
+    },
+    /// A skolemized region introduced when a `ForAll` over lifetimes is
+    /// instantiated. Two distinct placeholders never unify, and a
+    /// placeholder in universe `u` may only unify with an inference
+    /// variable whose universe can see `u`. This crate only models the
+    /// shape of that rule; it is the solver crate that walks this AST
+    /// (not present in this tree) that must enforce it when unifying.
+    Placeholder {
+        universe: u32,
+        index: u32,
+    },
+    Static,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Mutability {
+    Mut,
+    Not,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct ProjectionTy {
     pub trait_ref: TraitRef,
@@ -192,18 +316,21 @@ pub struct ProjectionTy {
     pub args: Vec<Parameter>,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct UnselectedProjectionTy {
     pub name: Identifier,
     pub args: Vec<Parameter>,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct TraitRef {
     pub trait_name: Identifier,
     pub args: Vec<Parameter>,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum PolarizedTraitRef {
     Positive(TraitRef),
@@ -220,20 +347,28 @@ impl PolarizedTraitRef {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Identifier {
+    #[cfg_attr(feature = "serde", serde(with = "interned_string_serde"))]
     pub str: InternedString,
     pub span: Span,
 }
 
+pub type WhereClause = Spanned<WhereClauseData>;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub enum WhereClause {
+pub enum WhereClauseData {
     Implemented { trait_ref: TraitRef },
     ProjectionEq { projection: ProjectionTy, ty: Ty },
 }
 
+pub type DomainGoal = Spanned<DomainGoalData>;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub enum DomainGoal {
+pub enum DomainGoalData {
     Holds { where_clause: WhereClause },
     Normalize { projection: ProjectionTy, ty: Ty },
     TraitRefWellFormed { trait_ref: TraitRef },
@@ -248,25 +383,38 @@ pub enum DomainGoal {
     LocalImplAllowed { trait_ref: TraitRef },
 }
 
+pub type LeafGoal = Spanned<LeafGoalData>;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub enum LeafGoal {
+pub enum LeafGoalData {
     DomainGoal { goal: DomainGoal },
     UnifyTys { a: Ty, b: Ty },
+    // Unification of two lifetimes. This is AST shape only: this crate
+    // does not implement unification. See `Lifetime::Placeholder` for the
+    // universe-visibility rule that the (out-of-tree) solver must enforce
+    // wherever it actually unifies two `Lifetime`s reached through this
+    // goal.
     UnifyLifetimes { a: Lifetime, b: Lifetime },
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct QuantifiedWhereClause {
     pub parameter_kinds: Vec<ParameterKind>,
     pub where_clause: WhereClause,
 }
 
+pub type Field = Spanned<FieldData>;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct Field {
+pub struct FieldData {
     pub name: Identifier,
     pub ty: Ty,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
 /// This allows users to add arbitrary `A :- B` clauses into the
 /// logic; it has no equivalent in Rust, but it's useful for testing.
@@ -276,8 +424,11 @@ pub struct Clause {
     pub conditions: Vec<Box<Goal>>,
 }
 
+pub type Goal = Spanned<GoalData>;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub enum Goal {
+pub enum GoalData {
     ForAll(Vec<ParameterKind>, Box<Goal>),
     Exists(Vec<ParameterKind>, Box<Goal>),
     Implies(Vec<Clause>, Box<Goal>),
@@ -285,5 +436,70 @@ pub enum Goal {
     Not(Box<Goal>),
 
     // Additional kinds of goals:
-    Leaf(LeafGoal),
+    Leaf(Box<LeafGoal>),
+}
+
+// `Span` is serialized as its resolved `lo`/`hi` offsets (not, say, the
+// `Copy` bit pattern), so it gets a hand-written impl to keep the JSON
+// self-contained and re-parseable.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::Span;
+    use serde::{Deserialize, Serialize};
+    use serde::ser::{SerializeStruct, Serializer};
+    use serde::de::Deserializer;
+
+    impl Serialize for Span {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut state = serializer.serialize_struct("Span", 2)?;
+            state.serialize_field("lo", &self.lo)?;
+            state.serialize_field("hi", &self.hi)?;
+            state.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Span {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct SpanData {
+                lo: usize,
+                hi: usize,
+            }
+
+            let data = SpanData::deserialize(deserializer)?;
+            Ok(Span::new(data.lo, data.hi))
+        }
+    }
+}
+
+// `InternedString` lives in `lalrpop_intern`, so the orphan rules forbid
+// implementing `Serialize`/`Deserialize` for it here directly. Instead this
+// is wired up per-field via `#[serde(with = "interned_string_serde")]` (see
+// `Identifier::str`), resolving it to the string it interns so the JSON is
+// self-contained and re-parseable without linking against the interner.
+#[cfg(feature = "serde")]
+mod interned_string_serde {
+    use lalrpop_intern::InternedString;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &InternedString, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<InternedString, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(::lalrpop_intern::intern(&s))
+    }
 }